@@ -0,0 +1,91 @@
+/// A handle returned by `Utreexo::checkpoint`, marking a point in the accumulator's update
+/// history. Pass it to `Utreexo::rewind` to undo every `update()` made since it was taken,
+/// e.g. to recover from a blockchain reorg.
+///
+/// A checkpoint becomes unusable once `Utreexo` has pruned it out of its bounded history
+/// window; `rewind` returns `Err(())` in that case. It also tags the `Utreexo` instance it
+/// was taken from, so passing it to a *different* accumulator is rejected rather than
+/// rewinding to whatever sequence number happens to match.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct Checkpoint {
+    sequence: u64,
+    accumulator_id: u64,
+}
+
+impl Checkpoint {
+    pub(crate) fn new(sequence: u64, accumulator_id: u64) -> Self {
+        Checkpoint {
+            sequence,
+            accumulator_id,
+        }
+    }
+
+    pub(crate) fn sequence(&self) -> u64 {
+        self.sequence
+    }
+
+    pub(crate) fn accumulator_id(&self) -> u64 {
+        self.accumulator_id
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::hasher::Sha256Hasher;
+    use crate::{hash, Utreexo};
+
+    #[test]
+    fn test_checkpoint_rewind_restores_roots() {
+        let mut acc = Utreexo::new(3);
+        acc.update(&[hash(b"a"), hash(b"b")], &[]).unwrap();
+
+        let checkpoint = acc.checkpoint();
+        let roots_before = acc.roots.clone();
+
+        acc.update(&[hash(b"c"), hash(b"d")], &[]).unwrap();
+        assert_ne!(acc.roots, roots_before);
+
+        acc.rewind(checkpoint).unwrap();
+        assert_eq!(acc.roots, roots_before);
+    }
+
+    #[test]
+    fn test_rewind_to_current_checkpoint_is_a_no_op() {
+        let mut acc = Utreexo::new(3);
+        acc.update(&[hash(b"a")], &[]).unwrap();
+
+        let checkpoint = acc.checkpoint();
+        let roots_before = acc.roots.clone();
+        acc.rewind(checkpoint).unwrap();
+        assert_eq!(acc.roots, roots_before);
+    }
+
+    #[test]
+    fn test_rewind_fails_once_checkpoint_falls_out_of_window() {
+        let mut acc = Utreexo::<Sha256Hasher>::with_checkpoint_window(3, 1);
+        acc.update(&[hash(b"a")], &[]).unwrap();
+
+        let checkpoint = acc.checkpoint();
+        acc.update(&[hash(b"b")], &[]).unwrap();
+        acc.update(&[hash(b"c")], &[]).unwrap();
+
+        assert!(acc.rewind(checkpoint).is_err());
+    }
+
+    #[test]
+    fn test_rewind_rejects_checkpoint_from_a_different_accumulator() {
+        let mut acc_a = Utreexo::new(3);
+        acc_a.update(&[hash(b"a")], &[]).unwrap();
+        let checkpoint_a = acc_a.checkpoint();
+
+        // Same capacity and the same number of `update()` calls, so `sequence` matches, but
+        // it's a distinct accumulator and should not accept `acc_a`'s checkpoint.
+        let mut acc_b = Utreexo::new(3);
+        acc_b.update(&[hash(b"b")], &[]).unwrap();
+        let roots_before = acc_b.roots.clone();
+
+        assert_eq!(checkpoint_a.sequence(), acc_b.checkpoint().sequence());
+        assert!(acc_b.rewind(checkpoint_a).is_err());
+        assert_eq!(acc_b.roots, roots_before);
+    }
+}
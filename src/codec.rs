@@ -0,0 +1,167 @@
+//! Canonical binary (de)serialization for `Utreexo`, `Proof`, and `BatchProof`, so accumulator
+//! state and proofs can be persisted or sent over the wire.
+
+use crate::hasher::MerkleHasher;
+
+/// The wire format version written by this build's `to_bytes` implementations. `from_bytes`
+/// rejects anything else with `CodecError::UnsupportedVersion`.
+pub(crate) const FORMAT_VERSION: u8 = 1;
+
+/// Errors produced while decoding a previously serialized `Utreexo`, `Proof`, or `BatchProof`.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum CodecError {
+    /// The input ended before a complete value could be read.
+    UnexpectedEof,
+    /// The leading version byte doesn't match a version this build understands.
+    UnsupportedVersion(u8),
+    /// A declared step/target/root count didn't match the bytes that followed it, or a root
+    /// marker byte was neither 0 (absent) nor 1 (present).
+    LengthMismatch,
+    /// A digest-sized byte slice was rejected by the hasher (corrupt data, or wrong backend).
+    InvalidDigest,
+}
+
+/// A cursor over a byte slice, used to decode the fixed-layout formats in this module.
+pub(crate) struct Reader<'a> {
+    bytes: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Reader<'a> {
+    pub(crate) fn new(bytes: &'a [u8]) -> Self {
+        Reader { bytes, pos: 0 }
+    }
+
+    pub(crate) fn read_u8(&mut self) -> Result<u8, CodecError> {
+        Ok(self.read_slice(1)?[0])
+    }
+
+    pub(crate) fn read_u32(&mut self) -> Result<u32, CodecError> {
+        let mut buf = [0u8; 4];
+        buf.copy_from_slice(self.read_slice(4)?);
+        Ok(u32::from_le_bytes(buf))
+    }
+
+    pub(crate) fn read_u64(&mut self) -> Result<u64, CodecError> {
+        let mut buf = [0u8; 8];
+        buf.copy_from_slice(self.read_slice(8)?);
+        Ok(u64::from_le_bytes(buf))
+    }
+
+    pub(crate) fn read_slice(&mut self, len: usize) -> Result<&'a [u8], CodecError> {
+        let end = self
+            .pos
+            .checked_add(len)
+            .ok_or(CodecError::LengthMismatch)?;
+        let slice = self
+            .bytes
+            .get(self.pos..end)
+            .ok_or(CodecError::UnexpectedEof)?;
+        self.pos = end;
+        Ok(slice)
+    }
+
+    pub(crate) fn read_digest<H: MerkleHasher>(&mut self) -> Result<H::Digest, CodecError> {
+        let slice = self.read_slice(H::DIGEST_SIZE)?;
+        H::digest_from_bytes(slice).map_err(|_| CodecError::InvalidDigest)
+    }
+
+    /// Reads a `u32` element count and checks it against the bytes actually left in the
+    /// input before the caller allocates a `Vec` sized by it, so a malformed/adversarial
+    /// count (e.g. `u32::MAX`) can't be used to trigger an out-of-memory abort.
+    /// `min_item_size` is the smallest number of bytes each element is known to occupy
+    /// (at least 1).
+    pub(crate) fn read_count(&mut self, min_item_size: usize) -> Result<usize, CodecError> {
+        let count = self.read_u32()? as usize;
+        if count > (self.bytes.len() - self.pos) / min_item_size {
+            return Err(CodecError::LengthMismatch);
+        }
+        Ok(count)
+    }
+
+    /// Checks that the version byte at the front of the input is one this build understands.
+    pub(crate) fn read_version(&mut self) -> Result<(), CodecError> {
+        let version = self.read_u8()?;
+        if version != FORMAT_VERSION {
+            return Err(CodecError::UnsupportedVersion(version));
+        }
+        Ok(())
+    }
+
+    /// Fails decoding if any trailing bytes are left over once a value has been fully read.
+    pub(crate) fn expect_exhausted(&self) -> Result<(), CodecError> {
+        if self.pos == self.bytes.len() {
+            Ok(())
+        } else {
+            Err(CodecError::LengthMismatch)
+        }
+    }
+}
+
+pub(crate) fn write_digest<H: MerkleHasher>(out: &mut Vec<u8>, digest: &H::Digest) {
+    out.extend_from_slice(&H::digest_to_bytes(digest));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_reader_round_trips_primitives() {
+        let mut bytes = vec![7u8];
+        bytes.extend_from_slice(&42u32.to_le_bytes());
+        bytes.extend_from_slice(&99u64.to_le_bytes());
+        bytes.extend_from_slice(&[1, 2, 3]);
+
+        let mut reader = Reader::new(&bytes);
+        assert_eq!(reader.read_u8().unwrap(), 7);
+        assert_eq!(reader.read_u32().unwrap(), 42);
+        assert_eq!(reader.read_u64().unwrap(), 99);
+        assert_eq!(reader.read_slice(3).unwrap(), &[1, 2, 3]);
+        assert!(reader.expect_exhausted().is_ok());
+    }
+
+    #[test]
+    fn test_reader_rejects_short_input() {
+        let bytes = [0u8; 3];
+        let mut reader = Reader::new(&bytes);
+        assert_eq!(reader.read_u32(), Err(CodecError::UnexpectedEof));
+    }
+
+    #[test]
+    fn test_expect_exhausted_rejects_trailing_bytes() {
+        let bytes = [0u8; 2];
+        let mut reader = Reader::new(&bytes);
+        reader.read_u8().unwrap();
+        assert_eq!(reader.expect_exhausted(), Err(CodecError::LengthMismatch));
+    }
+
+    #[test]
+    fn test_read_count_rejects_count_too_large_for_remaining_bytes() {
+        // Declares 2 four-byte items but leaves only 4 bytes behind the count.
+        let mut bytes = 2u32.to_le_bytes().to_vec();
+        bytes.extend_from_slice(&[0u8; 4]);
+
+        let mut reader = Reader::new(&bytes);
+        assert_eq!(reader.read_count(4), Err(CodecError::LengthMismatch));
+    }
+
+    #[test]
+    fn test_read_count_accepts_count_backed_by_remaining_bytes() {
+        let mut bytes = 2u32.to_le_bytes().to_vec();
+        bytes.extend_from_slice(&[0u8; 8]);
+
+        let mut reader = Reader::new(&bytes);
+        assert_eq!(reader.read_count(4).unwrap(), 2);
+    }
+
+    #[test]
+    fn test_read_version_rejects_unsupported_version() {
+        let bytes = [FORMAT_VERSION + 1];
+        let mut reader = Reader::new(&bytes);
+        assert_eq!(
+            reader.read_version(),
+            Err(CodecError::UnsupportedVersion(FORMAT_VERSION + 1))
+        );
+    }
+}
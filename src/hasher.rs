@@ -0,0 +1,74 @@
+use std::fmt::Debug;
+use std::hash::Hash as StdHash;
+
+use crate::{hash, Hash};
+
+/// The error returned when `MerkleHasher::digest_from_bytes` is given bytes that don't decode
+/// into a valid digest for that hasher (e.g. a byte string that isn't a valid curve point, for
+/// a hasher whose digest has internal structure beyond "N arbitrary bytes").
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct InvalidDigestBytes;
+
+/// A pluggable hash backend for the Merkle forest.
+///
+/// `Utreexo`, `Update`, `Proof` and `ProofStep` are generic over this trait so callers can
+/// plug in tagged hashes, Blake3, or a collision-friendly test hasher for exercising edge
+/// cases, instead of being tied to SHA-256.
+pub trait MerkleHasher {
+    /// The digest type stored at every node of the forest.
+    type Digest: Copy + Clone + Eq + StdHash + Debug;
+
+    /// Hashes raw leaf data into a digest.
+    fn hash_leaf(data: &[u8]) -> Self::Digest;
+
+    /// Hashes two child digests together into their parent's digest.
+    fn hash_nodes(left: &Self::Digest, right: &Self::Digest) -> Self::Digest;
+
+    /// Encoded width of `Digest`, in bytes. Used by the binary codec in `codec.rs`.
+    const DIGEST_SIZE: usize;
+
+    /// Encodes a digest for on-disk/wire storage.
+    fn digest_to_bytes(digest: &Self::Digest) -> Vec<u8>;
+
+    /// Decodes a digest previously produced by `digest_to_bytes`. `bytes` is always exactly
+    /// `DIGEST_SIZE` long; implementations only need to reject content they can't parse.
+    fn digest_from_bytes(bytes: &[u8]) -> Result<Self::Digest, InvalidDigestBytes>;
+}
+
+/// The default hash backend, using SHA-256 for both leaves and internal nodes.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Default)]
+pub struct Sha256Hasher;
+
+impl MerkleHasher for Sha256Hasher {
+    type Digest = Hash;
+
+    fn hash_leaf(data: &[u8]) -> Hash {
+        hash(data)
+    }
+
+    fn hash_nodes(left: &Hash, right: &Hash) -> Hash {
+        let concat = left
+            .0
+            .iter()
+            .chain(right.0.iter())
+            .copied()
+            .collect::<Vec<_>>();
+        hash(&concat[..])
+    }
+
+    const DIGEST_SIZE: usize = 32;
+
+    fn digest_to_bytes(digest: &Hash) -> Vec<u8> {
+        digest.0.to_vec()
+    }
+
+    fn digest_from_bytes(bytes: &[u8]) -> Result<Hash, InvalidDigestBytes> {
+        if bytes.len() != 32 {
+            return Err(InvalidDigestBytes);
+        }
+
+        let mut buf = [0u8; 32];
+        buf.copy_from_slice(bytes);
+        Ok(Hash(buf))
+    }
+}
@@ -1,10 +1,23 @@
-use crate::proof::{Proof, ProofStep};
-use sha2::{Digest, Sha256};
-use std::collections::HashMap;
+use sha2::{Digest as _, Sha256};
+use std::collections::{BTreeMap, HashMap, HashSet, VecDeque};
 use std::fmt::{Debug, Error as FmtError, Formatter};
+use std::marker::PhantomData;
+use std::sync::atomic::{AtomicU64, Ordering};
 
+use crate::checkpoint::Checkpoint;
+use crate::codec::{CodecError, Reader, FORMAT_VERSION};
+use crate::hasher::{MerkleHasher, Sha256Hasher};
+use crate::proof::{BatchProof, BatchTarget, Proof, ProofStatus, ProofStep};
+
+pub mod checkpoint;
+pub mod codec;
+pub mod hasher;
 pub mod proof;
 
+/// Number of past `update()` calls `Utreexo::new` keeps checkpoints for by default.
+/// Use `Utreexo::with_checkpoint_window` to configure a different bound.
+pub const DEFAULT_CHECKPOINT_WINDOW: usize = 100;
+
 fn hash(bytes: &[u8]) -> Hash {
     let mut sha = Sha256::new();
     sha.input(bytes);
@@ -15,6 +28,7 @@ fn hash(bytes: &[u8]) -> Hash {
     Hash(res_bytes)
 }
 
+/// The digest produced by the default `Sha256Hasher` backend.
 #[derive(Copy, Clone, Hash, Eq, PartialEq)]
 pub struct Hash(pub [u8; 32]);
 
@@ -68,55 +82,278 @@ impl Debug for Hash {
     }
 }
 
+/// How a forest root position changed as the result of an `update()` call.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum RootChange {
+    /// The position held no root before this update, and holds one now.
+    Added,
+    /// The position held a root before this update, and holds a different one now.
+    Modified,
+    /// The position held a root before this update, and holds none now.
+    Destroyed,
+}
+
 /// Updates made to the Utreexo accumulator, used to create proofs for inserted values.
-#[derive(Debug)]
-pub struct Update<'a> {
-    pub utreexo: &'a mut Utreexo,
-    pub updated: HashMap<Hash, ProofStep>,
+pub struct Update<'a, H: MerkleHasher = Sha256Hasher> {
+    pub utreexo: &'a mut Utreexo<H>,
+    pub updated: HashMap<H::Digest, ProofStep<H>>,
+
+    /// Root positions that were added, replaced, or emptied by this `update()` call, so a
+    /// wallet watching a subset of leaves can tell which cached proofs need refreshing
+    /// without re-checking every root.
+    pub changed_roots: Vec<(usize, RootChange)>,
+
+    /// Every internal node hash newly created while processing this update.
+    pub new_nodes: HashSet<H::Digest>,
 }
 
-impl<'a> Update<'a> {
+impl<'a, H: MerkleHasher> Debug for Update<'a, H> {
+    fn fmt(&self, f: &mut Formatter) -> Result<(), FmtError> {
+        f.debug_struct("Update")
+            .field("utreexo", &self.utreexo)
+            .field("updated", &self.updated)
+            .field("changed_roots", &self.changed_roots)
+            .field("new_nodes", &self.new_nodes)
+            .finish()
+    }
+}
+
+impl<'a, H: MerkleHasher> Update<'a, H> {
     /// Create a proof for an element if that element was inserted during this Utreexo update.
-    pub fn prove(&self, leaf: &Hash) -> Proof {
+    pub fn prove(&self, leaf: &H::Digest) -> Proof<H> {
         let mut proof = Proof {
             steps: vec![],
             leaf: *leaf,
+            position: 0,
         };
 
         let mut item = *leaf;
+        let mut height = 0u32;
         while let Some(s) = self.updated.get(&item) {
             proof.steps.push(*s);
+            if s.is_left {
+                proof.position |= 1 << height;
+            }
             item = self.utreexo.parent(&item, &s);
+            height += 1;
         }
 
         proof
     }
+
+    /// Creates an aggregated proof for many leaves at once, sharing sibling hashes that
+    /// more than one of them would otherwise repeat. See `Utreexo::verify_batch`.
+    pub fn prove_batch(&self, leaves: &[H::Digest]) -> BatchProof<H> {
+        let mut targets = Vec::with_capacity(leaves.len());
+        let mut by_height: BTreeMap<usize, BTreeMap<u64, Vec<ProofStep<H>>>> = BTreeMap::new();
+        let mut seen = HashSet::with_capacity(leaves.len());
+
+        for leaf in leaves {
+            // Two targets at the same (height, position) would collide when folded into
+            // `targets`/`by_height` below, producing a proof `verify_batch` always rejects.
+            // Dedupe instead of emitting something that can't verify.
+            if !seen.insert(*leaf) {
+                continue;
+            }
+
+            let single = self.prove(leaf);
+            let height = single.steps.len();
+
+            targets.push(BatchTarget {
+                leaf: *leaf,
+                height,
+                position: single.position,
+            });
+            by_height
+                .entry(height)
+                .or_default()
+                .insert(single.position, single.steps);
+        }
+
+        targets.sort_by_key(|t| (t.height, t.position));
+
+        let mut hashes = Vec::new();
+        for (height, mut row) in by_height {
+            for level in 0..height {
+                let positions: Vec<u64> = row.keys().cloned().collect();
+                let mut next_row = BTreeMap::new();
+                let mut consumed = HashSet::new();
+
+                for pos in positions {
+                    if consumed.contains(&pos) {
+                        continue;
+                    }
+                    consumed.insert(pos);
+
+                    let sibling = pos ^ 1;
+                    if row.contains_key(&sibling) {
+                        consumed.insert(sibling);
+                    } else {
+                        hashes.push(row[&pos][level].hash);
+                    }
+
+                    let steps = row.remove(&pos).unwrap();
+                    next_row.entry(pos >> 1).or_insert(steps);
+                }
+
+                row = next_row;
+            }
+        }
+
+        BatchProof { targets, hashes }
+    }
 }
 
+/// Source of the `id` that tags each `Utreexo` instance, so a `Checkpoint` taken from one
+/// accumulator can't be mistaken for one taken from another with the same `update_count`.
+static NEXT_ACCUMULATOR_ID: AtomicU64 = AtomicU64::new(0);
+
 /// A Utreexo accumulator. Holds array of Merkle forest roots.
-#[derive(Debug, Clone)]
-pub struct Utreexo {
-    pub roots: Vec<Option<Hash>>,
+pub struct Utreexo<H: MerkleHasher = Sha256Hasher> {
+    pub roots: Vec<Option<H::Digest>>,
+
+    /// Pre-update root snapshots for the last `checkpoint_window` calls to `update()`,
+    /// oldest first, each paired with the sequence number it was taken at. Used to rewind
+    /// to an earlier `Checkpoint`.
+    history: VecDeque<(u64, Vec<Option<H::Digest>>)>,
+    checkpoint_window: usize,
+    update_count: u64,
+
+    /// Uniquely identifies this accumulator instance, so `rewind` can reject a `Checkpoint`
+    /// taken from a different `Utreexo` even if its `update_count` happens to match.
+    id: u64,
+
+    _hasher: PhantomData<H>,
 }
 
-impl Utreexo {
+impl<H: MerkleHasher> Debug for Utreexo<H> {
+    fn fmt(&self, f: &mut Formatter) -> Result<(), FmtError> {
+        f.debug_struct("Utreexo")
+            .field("roots", &self.roots)
+            .finish()
+    }
+}
+
+impl<H: MerkleHasher> Clone for Utreexo<H> {
+    fn clone(&self) -> Self {
+        Utreexo {
+            roots: self.roots.clone(),
+            history: self.history.clone(),
+            checkpoint_window: self.checkpoint_window,
+            update_count: self.update_count,
+            id: NEXT_ACCUMULATOR_ID.fetch_add(1, Ordering::Relaxed),
+            _hasher: PhantomData,
+        }
+    }
+}
+
+// Split out of the generic `impl<H: MerkleHasher> Utreexo<H>` block below: a default type
+// parameter like `H: MerkleHasher = Sha256Hasher` isn't used by type inference, so a generic
+// `fn new` would leave existing call sites like `Utreexo::new(3)` unable to infer `H`. The same
+// applies to `from_bytes`: nothing in its `&[u8]` argument ties it to `H`, so it lives here too.
+impl Utreexo<Sha256Hasher> {
+    /// Creates a SHA-256-backed accumulator. To plug in a different `MerkleHasher`, use
+    /// `Utreexo::<H>::with_checkpoint_window` instead.
     pub fn new(capacity: usize) -> Self {
+        Self::with_checkpoint_window(capacity, DEFAULT_CHECKPOINT_WINDOW)
+    }
+
+    /// Decodes roots previously written by `to_bytes` into a fresh accumulator with
+    /// `DEFAULT_CHECKPOINT_WINDOW` checkpointing and no history.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, CodecError> {
+        let mut reader = Reader::new(bytes);
+        reader.read_version()?;
+
+        let count = reader.read_count(1)?;
+        let mut roots = Vec::with_capacity(count);
+        for _ in 0..count {
+            let root = match reader.read_u8()? {
+                0 => None,
+                1 => Some(reader.read_digest::<Sha256Hasher>()?),
+                _ => return Err(CodecError::LengthMismatch),
+            };
+            roots.push(root);
+        }
+        reader.expect_exhausted()?;
+
+        let mut utreexo = Self::with_checkpoint_window(0, DEFAULT_CHECKPOINT_WINDOW);
+        utreexo.roots = roots;
+        Ok(utreexo)
+    }
+}
+
+impl<H: MerkleHasher> Utreexo<H> {
+    /// Like `new`, but generic over the hash backend, and keeps checkpoints for the last
+    /// `checkpoint_window` calls to `update()` instead of `DEFAULT_CHECKPOINT_WINDOW`.
+    /// Pass `0` to disable checkpointing entirely.
+    pub fn with_checkpoint_window(capacity: usize, checkpoint_window: usize) -> Self {
         Utreexo {
             roots: vec![None; capacity],
+            history: VecDeque::new(),
+            checkpoint_window,
+            update_count: 0,
+            id: NEXT_ACCUMULATOR_ID.fetch_add(1, Ordering::Relaxed),
+            _hasher: PhantomData,
+        }
+    }
+
+    /// Returns a handle to the accumulator's current state. Pass it to `rewind` to undo
+    /// every `update()` made since, e.g. when a blockchain reorg is detected.
+    pub fn checkpoint(&self) -> Checkpoint {
+        Checkpoint::new(self.update_count, self.id)
+    }
+
+    /// Restores `self.roots` to the state it was in when `to` was taken, undoing every
+    /// `update()` made since. Fails if `to` has fallen out of the checkpoint window, was
+    /// never produced by this accumulator, or was produced by a different one.
+    pub fn rewind(&mut self, to: Checkpoint) -> Result<(), ()> {
+        if to.accumulator_id() != self.id {
+            return Err(());
+        }
+
+        if to.sequence() == self.update_count {
+            return Ok(());
+        }
+
+        let position = self
+            .history
+            .iter()
+            .position(|(sequence, _)| *sequence == to.sequence())
+            .ok_or(())?;
+
+        let (sequence, roots) = self.history[position].clone();
+        self.roots = roots;
+        self.update_count = sequence;
+        self.history.truncate(position);
+
+        Ok(())
+    }
+
+    /// Encodes the current roots as `[version: u8][root count: u32 LE]`, followed by one
+    /// `[marker: u8]` (0 = absent, 1 = present) per root, with a digest trailing present
+    /// markers. Checkpoint history is not part of the encoding.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut out = vec![FORMAT_VERSION];
+        out.extend_from_slice(&(self.roots.len() as u32).to_le_bytes());
+
+        for root in &self.roots {
+            match root {
+                Some(digest) => {
+                    out.push(1);
+                    crate::codec::write_digest::<H>(&mut out, digest);
+                }
+                None => out.push(0),
+            }
         }
+
+        out
     }
 
-    fn hash_pair(&self, left: &Hash, right: &Hash) -> Hash {
-        let concat = left
-            .0
-            .into_iter()
-            .chain(right.0.into_iter())
-            .map(|b| *b)
-            .collect::<Vec<_>>();
-        hash(&concat[..])
+    fn hash_pair(&self, left: &H::Digest, right: &H::Digest) -> H::Digest {
+        H::hash_nodes(left, right)
     }
 
-    fn parent(&self, h: &Hash, step: &ProofStep) -> Hash {
+    fn parent(&self, h: &H::Digest, step: &ProofStep<H>) -> H::Digest {
         if step.is_left {
             self.hash_pair(&step.hash, &h)
         } else {
@@ -124,7 +361,7 @@ impl Utreexo {
         }
     }
 
-    fn find_root(&self, root: &Hash, roots: &[Hash]) -> (usize, bool) {
+    fn find_root(&self, root: &H::Digest, roots: &[H::Digest]) -> (usize, bool) {
         for (i, r) in roots.iter().enumerate() {
             if root == r {
                 return (i, true);
@@ -134,7 +371,7 @@ impl Utreexo {
         (0, false)
     }
 
-    fn delete(&self, proof: &Proof, new_roots: &mut Vec<Vec<Hash>>) -> Result<(), ()> {
+    fn delete(&self, proof: &Proof<H>, new_roots: &mut Vec<Vec<H::Digest>>) -> Result<(), ()> {
         if self.roots.len() < proof.steps.len() || self.roots.get(proof.steps.len()).is_none() {
             return Err(());
         }
@@ -186,13 +423,13 @@ impl Utreexo {
 
     pub fn update<'a>(
         &'a mut self,
-        insertions: &[Hash],
-        deletions: &[Proof],
-    ) -> Result<Update<'a>, ()> {
+        insertions: &[H::Digest],
+        deletions: &[Proof<H>],
+    ) -> Result<Update<'a, H>, ()> {
         let mut new_roots = Vec::new();
 
         for root in self.roots.iter() {
-            let mut vec = Vec::<Hash>::new();
+            let mut vec = Vec::<H::Digest>::new();
             if let Some(hash) = root {
                 vec.push(*hash);
             }
@@ -200,7 +437,8 @@ impl Utreexo {
             new_roots.push(vec);
         }
 
-        let mut updated = HashMap::<Hash, ProofStep>::new();
+        let mut updated = HashMap::<H::Digest, ProofStep<H>>::new();
+        let mut new_nodes = HashSet::<H::Digest>::new();
 
         for d in deletions {
             self.delete(d, &mut new_roots)?;
@@ -219,6 +457,7 @@ impl Utreexo {
                 new_roots[i].pop();
 
                 let hash = self.hash_pair(&a, &b);
+                new_nodes.insert(hash);
 
                 // Grow the accumulator
                 if new_roots.len() <= i + 1 {
@@ -257,6 +496,18 @@ impl Utreexo {
             }
         }
 
+        // Record a checkpoint of the pre-update state before mutating `roots`.
+        if self.checkpoint_window > 0 {
+            self.history
+                .push_back((self.update_count, self.roots.clone()));
+            while self.history.len() > self.checkpoint_window {
+                self.history.pop_front();
+            }
+        }
+        self.update_count += 1;
+
+        let old_roots = self.roots.clone();
+
         // Apply new roots to the accumulator
         self.roots.truncate(to_take);
         for (i, bucket) in new_roots.into_iter().take(to_take).enumerate() {
@@ -271,13 +522,32 @@ impl Utreexo {
             }
         }
 
+        let mut changed_roots = Vec::new();
+        for i in 0..old_roots.len().max(self.roots.len()) {
+            let before = old_roots.get(i).copied().flatten();
+            let after = self.roots.get(i).copied().flatten();
+
+            let change = match (before, after) {
+                (None, Some(_)) => Some(RootChange::Added),
+                (Some(_), None) => Some(RootChange::Destroyed),
+                (Some(b), Some(a)) if b != a => Some(RootChange::Modified),
+                _ => None,
+            };
+
+            if let Some(change) = change {
+                changed_roots.push((i, change));
+            }
+        }
+
         Ok(Update {
             utreexo: self,
             updated,
+            changed_roots,
+            new_nodes,
         })
     }
 
-    pub fn verify(&self, proof: &Proof) -> bool {
+    pub fn verify(&self, proof: &Proof<H>) -> bool {
         let n = proof.steps.len();
         if n >= self.roots.len() {
             return false;
@@ -299,6 +569,126 @@ impl Utreexo {
             false
         }
     }
+
+    /// Like `verify`, but distinguishes a proof whose element has been deleted from one that
+    /// never authenticated anything.
+    ///
+    /// Recomputes the parent chain from `proof.leaf` exactly as `verify` does. If it matches
+    /// the live root at that height, the element is `Present`. Otherwise, the accumulator's
+    /// checkpoint history (see `checkpoint`/`rewind`) is searched for a past snapshot whose
+    /// root at that height matches: if one is found, the result is `Absent` (the element was
+    /// deleted, or the proof is stale and its element may still be present under a different
+    /// path); if none is found, the proof is `Invalid`. Checkpointing must
+    /// be enabled (`checkpoint_window > 0`) and the relevant update still within the window
+    /// for `Absent` to be distinguishable from `Invalid`.
+    pub fn verify_status(&self, proof: &Proof<H>) -> ProofStatus {
+        let n = proof.steps.len();
+
+        let mut current_parent = proof.leaf;
+        for s in proof.steps.iter() {
+            current_parent = if s.is_left {
+                self.hash_pair(&s.hash, &current_parent)
+            } else {
+                self.hash_pair(&current_parent, &s.hash)
+            };
+        }
+
+        if self.roots.get(n).copied().flatten() == Some(current_parent) {
+            return ProofStatus::Present;
+        }
+
+        // The root for this height may have shrunk out of `self.roots` entirely (e.g. a
+        // delete emptied the highest populated height), so `history` is searched regardless
+        // of whether `n` is still in bounds for the current roots.
+        let was_once_live = self
+            .history
+            .iter()
+            .any(|(_, roots)| roots.get(n).copied().flatten() == Some(current_parent));
+
+        if was_once_live {
+            ProofStatus::Absent
+        } else {
+            ProofStatus::Invalid
+        }
+    }
+
+    /// Verifies an aggregated `BatchProof` against the current roots.
+    ///
+    /// Reconstructs each affected root height-by-height from the bottom: for every node in
+    /// the current row, its sibling is either another node already in the row (in which case
+    /// the two are hashed together directly) or the next hash pulled off `proof.hashes`. This
+    /// mirrors `Update::prove_batch`'s consumption order exactly, so prover and verifier agree
+    /// on how `proof.hashes` maps onto the missing siblings.
+    pub fn verify_batch(&self, proof: &BatchProof<H>) -> bool {
+        let mut by_height: BTreeMap<usize, BTreeMap<u64, H::Digest>> = BTreeMap::new();
+        for target in &proof.targets {
+            if by_height
+                .entry(target.height)
+                .or_default()
+                .insert(target.position, target.leaf)
+                .is_some()
+            {
+                // Duplicate target at the same position.
+                return false;
+            }
+        }
+
+        let mut hashes = proof.hashes.iter();
+
+        for (height, mut row) in by_height {
+            if height >= self.roots.len() {
+                return false;
+            }
+            let expected = match self.roots[height] {
+                Some(expected) => expected,
+                None => return false,
+            };
+
+            for _ in 0..height {
+                let positions: Vec<u64> = row.keys().cloned().collect();
+                let mut next_row = BTreeMap::new();
+                let mut consumed = HashSet::new();
+
+                for pos in positions {
+                    if consumed.contains(&pos) {
+                        continue;
+                    }
+                    consumed.insert(pos);
+
+                    let sibling = pos ^ 1;
+                    let is_left = pos % 2 == 0;
+                    let this = row[&pos];
+
+                    let sibling_hash = if let Some(&h) = row.get(&sibling) {
+                        consumed.insert(sibling);
+                        h
+                    } else {
+                        match hashes.next() {
+                            Some(h) => *h,
+                            None => return false,
+                        }
+                    };
+
+                    let parent = if is_left {
+                        self.hash_pair(&this, &sibling_hash)
+                    } else {
+                        self.hash_pair(&sibling_hash, &this)
+                    };
+
+                    next_row.insert(pos >> 1, parent);
+                }
+
+                row = next_row;
+            }
+
+            match row.get(&0) {
+                Some(h) if *h == expected => {}
+                _ => return false,
+            }
+        }
+
+        true
+    }
 }
 
 #[cfg(test)]
@@ -336,6 +726,135 @@ mod tests {
         }
     }
 
+    #[test]
+    pub fn test_prove_batch_verify_batch() {
+        let mut acc = Utreexo::new(3);
+
+        let a = hash(b"a");
+        let b = hash(b"b");
+        let c = hash(b"c");
+        let d = hash(b"d");
+        let hashes = [a, b, c, d];
+
+        let update = acc.update(&hashes[..], &[]).unwrap();
+        let batch = update.prove_batch(&hashes);
+        let partial = update.prove_batch(&hashes[..2]);
+        let mut forged = update.prove_batch(&hashes[..1]);
+        forged.targets[0].leaf = hash(b"not-inserted");
+
+        assert!(acc.verify_batch(&batch));
+        // A batch proof for a subset of the leaves should still verify.
+        assert!(acc.verify_batch(&partial));
+        // A batch proof carrying a target that was never inserted should be rejected.
+        assert!(!acc.verify_batch(&forged));
+    }
+
+    #[test]
+    pub fn test_prove_batch_dedupes_repeated_leaves() {
+        let mut acc = Utreexo::new(3);
+
+        let a = hash(b"a");
+        let b = hash(b"b");
+        let update = acc.update(&[a, b], &[]).unwrap();
+
+        // A caller passing the same leaf twice (e.g. a duplicate in a block) shouldn't get
+        // back a proof that's unverifiable because of a (height, position) collision.
+        let batch = update.prove_batch(&[a, a, b]);
+        assert_eq!(batch.targets.len(), 2);
+        assert!(acc.verify_batch(&batch));
+    }
+
+    #[test]
+    pub fn test_utreexo_round_trip() {
+        let mut acc = Utreexo::new(3);
+        acc.update(&[hash(b"a"), hash(b"b")], &[]).unwrap();
+
+        let bytes = acc.to_bytes();
+        let decoded = Utreexo::from_bytes(&bytes).unwrap();
+
+        assert_eq!(decoded.roots, acc.roots);
+    }
+
+    #[test]
+    pub fn test_changed_roots_and_new_nodes() {
+        use crate::RootChange;
+
+        let mut acc = Utreexo::new(3);
+
+        let a = hash(b"a");
+        let b = hash(b"b");
+        let update = acc.update(&[a, b], &[]).unwrap();
+
+        // Inserting two leaves merges them into a single new root, at position 1.
+        assert_eq!(update.changed_roots, vec![(1, RootChange::Added)]);
+        assert!(!update.new_nodes.is_empty());
+
+        let proof_a = update.prove(&a);
+        let update = acc.update(&[], std::slice::from_ref(&proof_a)).unwrap();
+
+        // Deleting one of the two leaves under that root destroys it, leaving the
+        // remaining leaf as a new root at position 0.
+        assert_eq!(
+            update.changed_roots,
+            vec![(0, RootChange::Added), (1, RootChange::Destroyed)]
+        );
+    }
+
+    #[test]
+    pub fn test_verify_status_present() {
+        use crate::proof::ProofStatus;
+
+        let mut acc = Utreexo::new(3);
+        let a = hash(b"a");
+        let update = acc.update(&[a], &[]).unwrap();
+        let proof = update.prove(&a);
+
+        assert_eq!(acc.verify_status(&proof), ProofStatus::Present);
+    }
+
+    #[test]
+    pub fn test_verify_status_invalid_for_proof_that_never_authenticated_anything() {
+        use crate::proof::ProofStatus;
+
+        let mut acc = Utreexo::new(3);
+        let a = hash(b"a");
+        let update = acc.update(&[a], &[]).unwrap();
+        let mut forged = update.prove(&a);
+        forged.leaf = hash(b"not-inserted");
+
+        assert_eq!(acc.verify_status(&forged), ProofStatus::Invalid);
+    }
+
+    #[test]
+    pub fn test_verify_status_absent_after_delete() {
+        use crate::proof::ProofStatus;
+
+        let mut acc = Utreexo::new(3);
+        let a = hash(b"a");
+        let b = hash(b"b");
+        let c = hash(b"c");
+        let d = hash(b"d");
+
+        let (proof_a, proof_b, proof_c, proof_d) = {
+            let update = acc.update(&[a, b, c, d], &[]).unwrap();
+            (
+                update.prove(&a),
+                update.prove(&b),
+                update.prove(&c),
+                update.prove(&d),
+            )
+        };
+
+        // Deleting every leaf empties the only populated root, which truncates `self.roots`
+        // below the height `proof_a` recomputes to (see `verify_status`'s doc comment) —
+        // exercises the "root shrunk out of `self.roots` entirely" case explicitly.
+        acc.update(&[], &[proof_a.clone(), proof_b, proof_c, proof_d])
+            .unwrap();
+        assert!(acc.roots.len() <= proof_a.steps.len());
+
+        assert_eq!(acc.verify_status(&proof_a), ProofStatus::Absent);
+    }
+
     // Test for accumulator overflow is handled. Note that this test may be slow.
     #[test]
     pub fn test_add_exceed() {
@@ -1,22 +1,70 @@
-use crate::{Digest, Update};
+use std::fmt::{Debug, Error as FmtError, Formatter};
+
+use crate::codec::{CodecError, Reader, FORMAT_VERSION};
+use crate::hasher::{MerkleHasher, Sha256Hasher};
+use crate::Update;
 
 /// Defines a single step of Merkle Proof of inclusion.
-#[derive(Debug, Copy, Clone)]
-pub struct ProofStep {
-    pub hash: Digest,
+pub struct ProofStep<H: MerkleHasher = Sha256Hasher> {
+    pub hash: H::Digest,
     pub is_left: bool,
 }
 
+// Written by hand rather than derived: `#[derive(Copy, Clone)]` would add a spurious
+// `H: Copy`/`H: Clone` bound, when all that's actually needed is `H::Digest: Copy`,
+// already guaranteed by `MerkleHasher`.
+impl<H: MerkleHasher> Copy for ProofStep<H> {}
+
+impl<H: MerkleHasher> Clone for ProofStep<H> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<H: MerkleHasher> Debug for ProofStep<H> {
+    fn fmt(&self, f: &mut Formatter) -> Result<(), FmtError> {
+        f.debug_struct("ProofStep")
+            .field("hash", &self.hash)
+            .field("is_left", &self.is_left)
+            .finish()
+    }
+}
+
 /// Defines the Merkle Proof of inclusion for a specific element in the Utreexo accumulator.
-#[derive(Debug, Clone)]
-pub struct Proof {
-    pub steps: Vec<ProofStep>,
-    pub leaf: Digest,
+pub struct Proof<H: MerkleHasher = Sha256Hasher> {
+    pub steps: Vec<ProofStep<H>>,
+    pub leaf: H::Digest,
+
+    /// Position of `leaf` among the `2^steps.len()` leaves of the root it authenticates,
+    /// bit `i` telling whether the leaf is the right child (1) or left child (0) at height `i`.
+    /// Used to line up shared siblings when several proofs are folded into a `BatchProof`.
+    pub position: u64,
+}
+
+// Written by hand to avoid the spurious `H: Clone` bound `#[derive(Clone)]` would add.
+impl<H: MerkleHasher> Clone for Proof<H> {
+    fn clone(&self) -> Self {
+        Proof {
+            steps: self.steps.clone(),
+            leaf: self.leaf,
+            position: self.position,
+        }
+    }
+}
+
+impl<H: MerkleHasher> Debug for Proof<H> {
+    fn fmt(&self, f: &mut Formatter) -> Result<(), FmtError> {
+        f.debug_struct("Proof")
+            .field("steps", &self.steps)
+            .field("leaf", &self.leaf)
+            .field("position", &self.position)
+            .finish()
+    }
 }
 
-impl Proof {
+impl<H: MerkleHasher> Proof<H> {
     /// Updates proof when accumulator state changes. Change is reflected via `Update` structure.
-    pub fn update(&mut self, update: &Update) -> Result<(), ()> {
+    pub fn update(&mut self, update: &Update<H>) -> Result<(), ()> {
         let mut h = self.leaf;
         for i in 0..=self.steps.len() {
             if update.utreexo.roots.len() > i
@@ -24,21 +72,21 @@ impl Proof {
                     .utreexo
                     .roots
                     .get(i)
-                    .and_then(|root| {
-                        Some(
-                            root.and_then(|rh| Some(rh.as_ref() == h.as_ref()))
-                                .unwrap_or(false),
-                        )
-                    })
+                    .map(|root| root.map(|rh| rh == h).unwrap_or(false))
                     .unwrap_or(false)
             {
                 self.steps.truncate(i);
+                self.position &= (1u64 << i) - 1;
                 return Ok(());
             }
 
-            let step = if let Some(step) = update.updated.get(h.as_ref()) {
+            let step = if let Some(step) = update.updated.get(&h) {
                 self.steps.truncate(i);
                 self.steps.push(*step);
+                self.position &= (1u64 << i) - 1;
+                if step.is_left {
+                    self.position |= 1 << i;
+                }
 
                 *step
             } else if i == self.steps.len() {
@@ -52,4 +100,264 @@ impl Proof {
 
         Ok(())
     }
+
+    /// Encodes this proof as `[version: u8][leaf][position: u64 LE][step count: u32 LE]`,
+    /// followed by a bitmap (one bit per step, set if `is_left`) and then each step's
+    /// sibling digest in order.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut out = vec![FORMAT_VERSION];
+        crate::codec::write_digest::<H>(&mut out, &self.leaf);
+        out.extend_from_slice(&self.position.to_le_bytes());
+        out.extend_from_slice(&(self.steps.len() as u32).to_le_bytes());
+
+        let mut bitmap = vec![0u8; self.steps.len().div_ceil(8)];
+        for (i, step) in self.steps.iter().enumerate() {
+            if step.is_left {
+                bitmap[i / 8] |= 1 << (i % 8);
+            }
+        }
+        out.extend_from_slice(&bitmap);
+
+        for step in &self.steps {
+            crate::codec::write_digest::<H>(&mut out, &step.hash);
+        }
+
+        out
+    }
+
+    /// Decodes a proof previously written by `to_bytes`.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, CodecError> {
+        let mut reader = Reader::new(bytes);
+        reader.read_version()?;
+
+        let leaf = reader.read_digest::<H>()?;
+        let position = reader.read_u64()?;
+        let step_count = reader.read_count(1)?;
+
+        let bitmap = reader.read_slice(step_count.div_ceil(8))?;
+        let mut steps = Vec::with_capacity(step_count);
+        for i in 0..step_count {
+            let is_left = bitmap[i / 8] & (1 << (i % 8)) != 0;
+            let hash = reader.read_digest::<H>()?;
+            steps.push(ProofStep { hash, is_left });
+        }
+        reader.expect_exhausted()?;
+
+        Ok(Proof {
+            steps,
+            leaf,
+            position,
+        })
+    }
+}
+
+/// The outcome of `Utreexo::verify_status` for a single `Proof`.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum ProofStatus {
+    /// The proof's leaf recomputes to a live root: the element is currently in the accumulator.
+    Present,
+    /// The recomputed parent chain matches a root the accumulator held at this height in the
+    /// past, but that root is no longer live: either the element was deleted, or the proof is
+    /// stale (the root was emptied or replaced by a later `update()` and the proof was not
+    /// brought up to date with `Proof::update`). These two cases are not told apart. Only
+    /// distinguishable from `Invalid` while the relevant pre-update snapshot is still within
+    /// the accumulator's checkpoint window.
+    Absent,
+    /// The recomputed parent chain doesn't match any root the accumulator is known to have
+    /// held at this height: the proof never authenticated anything.
+    Invalid,
+}
+
+/// A single leaf authenticated by a `BatchProof`, together with the coordinates needed to
+/// place it in the forest: the height of the root it belongs to, and its `position` among
+/// that root's `2^height` leaves (see `Proof::position`).
+pub struct BatchTarget<H: MerkleHasher = Sha256Hasher> {
+    pub leaf: H::Digest,
+    pub height: usize,
+    pub position: u64,
+}
+
+impl<H: MerkleHasher> Copy for BatchTarget<H> {}
+
+impl<H: MerkleHasher> Clone for BatchTarget<H> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<H: MerkleHasher> Debug for BatchTarget<H> {
+    fn fmt(&self, f: &mut Formatter) -> Result<(), FmtError> {
+        f.debug_struct("BatchTarget")
+            .field("leaf", &self.leaf)
+            .field("height", &self.height)
+            .field("position", &self.position)
+            .finish()
+    }
+}
+
+/// An aggregated membership proof for many leaves at once.
+///
+/// Instead of repeating sibling hashes that several `Proof`s would otherwise share,
+/// a `BatchProof` lists its `targets` (sorted by height, then position) plus the flat,
+/// deduplicated set of extra node hashes still needed to recompute the affected roots.
+/// `hashes` must be consumed in the same deterministic order they were produced in by
+/// `Update::prove_batch`: height groups ascending, and within a group targets in ascending
+/// position order, left sibling before right at each merge.
+pub struct BatchProof<H: MerkleHasher = Sha256Hasher> {
+    pub targets: Vec<BatchTarget<H>>,
+    pub hashes: Vec<H::Digest>,
+}
+
+impl<H: MerkleHasher> Clone for BatchProof<H> {
+    fn clone(&self) -> Self {
+        BatchProof {
+            targets: self.targets.clone(),
+            hashes: self.hashes.clone(),
+        }
+    }
+}
+
+impl<H: MerkleHasher> Debug for BatchProof<H> {
+    fn fmt(&self, f: &mut Formatter) -> Result<(), FmtError> {
+        f.debug_struct("BatchProof")
+            .field("targets", &self.targets)
+            .field("hashes", &self.hashes)
+            .finish()
+    }
+}
+
+impl<H: MerkleHasher> BatchProof<H> {
+    /// Encodes this batch proof as `[version: u8][target count: u32 LE]`, followed by each
+    /// target as `[height: u32 LE][position: u64 LE][leaf]`, then `[hash count: u32 LE]` and
+    /// the extra node digests in order.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut out = vec![FORMAT_VERSION];
+        out.extend_from_slice(&(self.targets.len() as u32).to_le_bytes());
+
+        for target in &self.targets {
+            out.extend_from_slice(&(target.height as u32).to_le_bytes());
+            out.extend_from_slice(&target.position.to_le_bytes());
+            crate::codec::write_digest::<H>(&mut out, &target.leaf);
+        }
+
+        out.extend_from_slice(&(self.hashes.len() as u32).to_le_bytes());
+        for hash in &self.hashes {
+            crate::codec::write_digest::<H>(&mut out, hash);
+        }
+
+        out
+    }
+
+    /// Decodes a batch proof previously written by `to_bytes`.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, CodecError> {
+        let mut reader = Reader::new(bytes);
+        reader.read_version()?;
+
+        let target_count = reader.read_count(4 + 8 + H::DIGEST_SIZE)?;
+        let mut targets = Vec::with_capacity(target_count);
+        for _ in 0..target_count {
+            let height = reader.read_u32()? as usize;
+            let position = reader.read_u64()?;
+            let leaf = reader.read_digest::<H>()?;
+            targets.push(BatchTarget {
+                leaf,
+                height,
+                position,
+            });
+        }
+
+        let hash_count = reader.read_count(H::DIGEST_SIZE)?;
+        let mut hashes = Vec::with_capacity(hash_count);
+        for _ in 0..hash_count {
+            hashes.push(reader.read_digest::<H>()?);
+        }
+        reader.expect_exhausted()?;
+
+        Ok(BatchProof { targets, hashes })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{hash, Utreexo};
+
+    #[test]
+    fn test_proof_round_trip() {
+        let mut acc = Utreexo::new(3);
+        let a = hash(b"a");
+        let update = acc.update(&[a, hash(b"b")], &[]).unwrap();
+        let proof = update.prove(&a);
+
+        let bytes = proof.to_bytes();
+        let decoded = Proof::<Sha256Hasher>::from_bytes(&bytes).unwrap();
+
+        assert_eq!(decoded.leaf, proof.leaf);
+        assert_eq!(decoded.position, proof.position);
+        assert_eq!(decoded.steps.len(), proof.steps.len());
+        assert!(acc.verify(&decoded));
+    }
+
+    #[test]
+    fn test_proof_from_bytes_rejects_truncated_input() {
+        let mut acc = Utreexo::new(3);
+        let a = hash(b"a");
+        let update = acc.update(&[a, hash(b"b")], &[]).unwrap();
+        let bytes = update.prove(&a).to_bytes();
+
+        for len in 0..bytes.len() {
+            assert!(Proof::<Sha256Hasher>::from_bytes(&bytes[..len]).is_err());
+        }
+    }
+
+    #[test]
+    fn test_proof_from_bytes_rejects_oversized_step_count() {
+        let mut acc = Utreexo::new(3);
+        let a = hash(b"a");
+        let update = acc.update(&[a, hash(b"b")], &[]).unwrap();
+        let mut bytes = update.prove(&a).to_bytes();
+
+        // Overwrite the step count field (right after [version][leaf][position]) with an
+        // enormous value that couldn't possibly be backed by the remaining bytes.
+        let step_count_offset = 1 + Sha256Hasher::DIGEST_SIZE + 8;
+        bytes[step_count_offset..step_count_offset + 4].copy_from_slice(&u32::MAX.to_le_bytes());
+
+        assert_eq!(
+            Proof::<Sha256Hasher>::from_bytes(&bytes).unwrap_err(),
+            CodecError::LengthMismatch
+        );
+    }
+
+    #[test]
+    fn test_batch_proof_round_trip() {
+        let mut acc = Utreexo::new(3);
+        let a = hash(b"a");
+        let b = hash(b"b");
+        let update = acc.update(&[a, b], &[]).unwrap();
+        let batch = update.prove_batch(&[a, b]);
+
+        let bytes = batch.to_bytes();
+        let decoded = BatchProof::<Sha256Hasher>::from_bytes(&bytes).unwrap();
+
+        assert_eq!(decoded.targets.len(), batch.targets.len());
+        assert_eq!(decoded.hashes, batch.hashes);
+        assert!(acc.verify_batch(&decoded));
+    }
+
+    #[test]
+    fn test_batch_proof_from_bytes_rejects_oversized_target_count() {
+        let mut acc = Utreexo::new(3);
+        let a = hash(b"a");
+        let update = acc.update(&[a, hash(b"b")], &[]).unwrap();
+        let mut bytes = update.prove_batch(&[a]).to_bytes();
+
+        // Overwrite the target count field (right after the version byte) with an enormous
+        // value that couldn't possibly be backed by the remaining bytes.
+        bytes[1..5].copy_from_slice(&u32::MAX.to_le_bytes());
+
+        assert_eq!(
+            BatchProof::<Sha256Hasher>::from_bytes(&bytes).unwrap_err(),
+            CodecError::LengthMismatch
+        );
+    }
 }